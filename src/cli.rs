@@ -0,0 +1,233 @@
+use std::{
+    ffi::{OsStr, OsString},
+    os::unix::ffi::OsStrExt,
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, bail};
+
+use crate::DefaultRole;
+
+// command-line overrides for the paths and dart options that `run` is given.
+// this is deliberately tiny; pulling in clap just for a handful of flags isn't worth it.
+#[derive(Debug, Default)]
+pub struct Args {
+    pub assets: Option<PathBuf>,
+    pub app_library: Option<PathBuf>,
+    pub dart_entrypoint: Option<String>,
+    pub dart_args: Vec<String>,
+    pub default_role: Option<DefaultRole>,
+}
+
+impl Args {
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::parse(std::env::args_os().skip(1))
+    }
+
+    // paths are taken as-is, since they needn't be UTF-8. anything that ends up in dart must be.
+    pub fn parse(args: impl IntoIterator<Item = OsString>) -> anyhow::Result<Self> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            // everything after a bare `--` is handed to the dart entrypoint verbatim
+            if arg == "--" {
+                for arg in args {
+                    parsed.dart_args.push(arg.into_string().map_err(|arg| {
+                        anyhow!(
+                            "dart arguments must be valid UTF-8, got {}",
+                            arg.to_string_lossy()
+                        )
+                    })?);
+                }
+                break;
+            }
+
+            // accept both `--flag value` and `--flag=value`
+            let bytes = arg.as_bytes();
+            let (flag, mut inline_value) = match bytes.iter().position(|&b| b == b'=') {
+                Some(eq) if bytes.starts_with(b"--") => (
+                    OsStr::from_bytes(&bytes[..eq]).to_owned(),
+                    Some(OsStr::from_bytes(&bytes[eq + 1..]).to_owned()),
+                ),
+                _ => (arg, None),
+            };
+
+            let flag = flag
+                .into_string()
+                .map_err(|flag| anyhow!("unrecognized argument: {}", flag.to_string_lossy()))?;
+
+            let mut value = || {
+                inline_value
+                    .take()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| anyhow!("{flag} expects a value"))
+            };
+
+            match flag.as_str() {
+                "--assets" => parsed.assets = Some(non_empty(&flag, value()?)?.into()),
+                "--app-library" => parsed.app_library = Some(non_empty(&flag, value()?)?.into()),
+                "--dart-entrypoint" => {
+                    let entrypoint = utf8(&flag, non_empty(&flag, value()?)?)?;
+                    if !is_dart_identifier(&entrypoint) {
+                        bail!("{entrypoint:?} is not a valid dart identifier");
+                    }
+                    parsed.dart_entrypoint = Some(entrypoint);
+                }
+                "--default-role" => {
+                    parsed.default_role = Some(match utf8(&flag, value()?)?.as_str() {
+                        "toplevel" => DefaultRole::Toplevel,
+                        "fullscreen" => DefaultRole::FullscreenToplevel,
+                        "background" => DefaultRole::BackgroundLayer,
+                        "top" => DefaultRole::TopLayer,
+                        "overlay" => DefaultRole::OverlayLayer,
+                        role => bail!(
+                            "{flag} expects one of toplevel, fullscreen, background, top or overlay, got {role:?}"
+                        ),
+                    });
+                }
+                // an empty string is a perfectly good argument to pass along
+                "--dart-arg" => parsed.dart_args.push(utf8(&flag, value()?)?),
+                _ => bail!("unrecognized argument: {flag}"),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+fn non_empty(flag: &str, value: OsString) -> anyhow::Result<OsString> {
+    if value.is_empty() {
+        bail!("{flag} expects a non-empty value");
+    }
+    Ok(value)
+}
+
+fn utf8(flag: &str, value: OsString) -> anyhow::Result<String> {
+    value.into_string().map_err(|value| {
+        anyhow!(
+            "{flag} expects valid UTF-8, got {}",
+            value.to_string_lossy()
+        )
+    })
+}
+
+// these can't name a function at all; built-in identifiers like `get` or `async` can, so they're not here.
+const DART_RESERVED_WORDS: &[&str] = &[
+    "assert", "break", "case", "catch", "class", "const", "continue", "default", "do", "else",
+    "enum", "extends", "false", "final", "finally", "for", "if", "in", "is", "new", "null",
+    "rethrow", "return", "super", "switch", "this", "throw", "true", "try", "var", "void", "while",
+    "with",
+];
+
+// the engine looks the entrypoint up by name and fails much later (and much less legibly) if it's garbage.
+fn is_dart_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !DART_RESERVED_WORDS.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Args> {
+        Args::parse(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn separate_and_inline_values() {
+        let args = parse(&[
+            "--assets",
+            "/a",
+            "--app-library=/lib.so",
+            "--dart-arg",
+            "x",
+            "--dart-arg=y",
+        ])
+        .unwrap();
+
+        assert_eq!(args.assets, Some(PathBuf::from("/a")));
+        assert_eq!(args.app_library, Some(PathBuf::from("/lib.so")));
+        assert_eq!(args.dart_args, ["x", "y"]);
+    }
+
+    #[test]
+    fn missing_value() {
+        assert!(parse(&["--assets"]).is_err());
+        assert!(parse(&["--dart-arg"]).is_err());
+    }
+
+    #[test]
+    fn empty_value() {
+        assert!(parse(&["--assets="]).is_err());
+        assert!(parse(&["--app-library", ""]).is_err());
+        assert!(parse(&["--dart-entrypoint="]).is_err());
+
+        let args = parse(&["--dart-arg", "", "--dart-arg=", "--", ""]).unwrap();
+        assert_eq!(args.dart_args, ["", "", ""]);
+    }
+
+    #[test]
+    fn non_utf8() {
+        let invalid = OsStr::from_bytes(b"/nelly/\xff");
+
+        let args = Args::parse([OsString::from("--assets"), invalid.to_owned()]).unwrap();
+        assert_eq!(args.assets.as_deref(), Some(invalid.as_ref()));
+
+        let mut inline = OsString::from("--app-library=");
+        inline.push(invalid);
+        let args = Args::parse([inline]).unwrap();
+        assert_eq!(args.app_library.as_deref(), Some(invalid.as_ref()));
+
+        assert!(Args::parse([OsString::from("--dart-arg"), invalid.to_owned()]).is_err());
+        assert!(Args::parse([OsString::from("--"), invalid.to_owned()]).is_err());
+    }
+
+    #[test]
+    fn default_role() {
+        let args = parse(&["--default-role", "background"]).unwrap();
+        assert_eq!(args.default_role, Some(DefaultRole::BackgroundLayer));
+
+        let args = parse(&["--default-role=fullscreen"]).unwrap();
+        assert_eq!(args.default_role, Some(DefaultRole::FullscreenToplevel));
+
+        assert!(parse(&["--default-role", "bottom"]).is_err());
+        assert!(parse(&["--default-role="]).is_err());
+    }
+
+    #[test]
+    fn unknown_flag() {
+        assert!(parse(&["--verbose"]).is_err());
+        assert!(parse(&["--verbose=1"]).is_err());
+        assert!(parse(&["assets"]).is_err());
+    }
+
+    #[test]
+    fn passthrough() {
+        let args = parse(&["--dart-arg", "x", "--", "--assets", "y", "--"]).unwrap();
+
+        assert_eq!(args.assets, None);
+        assert_eq!(args.dart_args, ["x", "--assets", "y", "--"]);
+    }
+
+    #[test]
+    fn dart_entrypoint() {
+        let args = parse(&["--dart-entrypoint", "_my$entrypoint1"]).unwrap();
+        assert_eq!(args.dart_entrypoint.as_deref(), Some("_my$entrypoint1"));
+
+        assert!(parse(&["--dart-entrypoint", "1st"]).is_err());
+        assert!(parse(&["--dart-entrypoint", "my-entrypoint"]).is_err());
+        assert!(parse(&["--dart-entrypoint", "main()"]).is_err());
+        assert!(parse(&["--dart-entrypoint", "class"]).is_err());
+        assert!(parse(&["--dart-entrypoint", "if"]).is_err());
+        assert!(parse(&["--dart-entrypoint", "void"]).is_err());
+
+        // built-in identifiers are fine as function names
+        let args = parse(&["--dart-entrypoint", "get"]).unwrap();
+        assert_eq!(args.dart_entrypoint.as_deref(), Some("get"));
+    }
+}
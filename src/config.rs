@@ -1,9 +1,34 @@
 use std::sync::{Arc, Mutex};
 
-pub struct Config {}
+#[derive(Debug, Default)]
+pub struct Config {
+    /// What kind of surface the Dart half puts its first view on.
+    pub default_role: DefaultRole,
+}
 
 impl Config {
+    #[must_use]
     pub fn load() -> Arc<Mutex<Self>> {
-        Arc::new(Mutex::new(Self {}))
+        Arc::new(Mutex::new(Self::default()))
     }
 }
+
+/// The shell role of the view the Dart half creates at startup.
+///
+/// This is sent to Dart as its discriminant, so the order must match `DefaultRole` in `default_role.dart`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DefaultRole {
+    /// A normal `xdg_toplevel` window.
+    #[default]
+    Toplevel,
+    /// An `xdg_toplevel` that asks to be fullscreen.
+    ///
+    /// Halcyon's toplevels can't ask for that yet, so for now this gets a normal window.
+    FullscreenToplevel,
+    /// A layer-shell surface on the background layer, e.g. for a kiosk.
+    BackgroundLayer,
+    /// A layer-shell surface on the top layer.
+    TopLayer,
+    /// A layer-shell surface on the overlay layer.
+    OverlayLayer,
+}
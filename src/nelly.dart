@@ -1,21 +1,28 @@
 // ignore_for_file: prefer_const_constructors
 
+import "dart:async";
 import "dart:ui";
 
 import "package:flutter/material.dart";
 import "package:halcyon/widgets/wlr_layer.dart";
 import "package:halcyon/widgets/xdg_toplevel.dart";
 
+import "platform_message/default_role.dart";
+import "platform_message/handshake.dart";
 import "platform_message/mod.dart" as platform_message;
 import "platform_message/shutdown.dart";
 
-void run() {
+Future<void> run() async {
   platform_message.initListeners();
-  runWidget(const Nelly());
+  unawaited(handshake());
+  // the first view's role can't change once it exists, so it has to be known before the first build.
+  runWidget(Nelly(role: await defaultRole()));
 }
 
 class Nelly extends StatefulWidget {
-  const Nelly({super.key});
+  const Nelly({super.key, required this.role});
+
+  final DefaultRole role;
 
   @override
   State<Nelly> createState() => _NellyState();
@@ -28,42 +35,53 @@ class _NellyState extends State<Nelly> {
   Widget build(BuildContext context) {
     return ViewCollection(
       views: [
-        // WlrLayerSurface(
-        //   layer: Layer.top,
-        //   namespace: "nelly",
-        //   child: Container(color: Colors.red),
-        // ),
-        XdgToplevelSurface(
-          key: xdgKey,
-          title: "nelly",
-          appId: "nelly",
-          onClose: () {
-            gracefulShutdown();
-          },
-          // viewConstraints: ViewConstraints(
-          //   minWidth: 350,
-          //   minHeight: 200,
-          //   maxWidth: 900,
-          //   maxHeight: 800,
-          // ),
-          // child: const DemoApp(),
-          child: ConstrainedBox(
-            constraints: const BoxConstraints(
-                // minWidth: 350,
-                // minHeight: 200,
-                // maxHeight: 800,
-                // maxWidth: 900,
-                ),
-            // child: Directionality(
-            //   textDirection: TextDirection.ltr,
-            //   child: const Text("Hello, Nelly!"),
-            // ),
-            child: const DemoApp(),
-          ),
-        ),
+        switch (widget.role) {
+          DefaultRole.backgroundLayer => layerSurface(Layer.background),
+          DefaultRole.topLayer => layerSurface(Layer.top),
+          DefaultRole.overlayLayer => layerSurface(Layer.overlay),
+          // XdgToplevelSurface has no way to ask for fullscreen yet; until halcyon grows one, it's a plain toplevel.
+          DefaultRole.toplevel ||
+          DefaultRole.fullscreenToplevel =>
+            XdgToplevelSurface(
+              key: xdgKey,
+              title: "nelly",
+              appId: "nelly",
+              onClose: () {
+                gracefulShutdown();
+              },
+              // viewConstraints: ViewConstraints(
+              //   minWidth: 350,
+              //   minHeight: 200,
+              //   maxWidth: 900,
+              //   maxHeight: 800,
+              // ),
+              // child: const DemoApp(),
+              child: ConstrainedBox(
+                constraints: const BoxConstraints(
+                    // minWidth: 350,
+                    // minHeight: 200,
+                    // maxHeight: 800,
+                    // maxWidth: 900,
+                    ),
+                // child: Directionality(
+                //   textDirection: TextDirection.ltr,
+                //   child: const Text("Hello, Nelly!"),
+                // ),
+                child: const DemoApp(),
+              ),
+            ),
+        },
       ],
     );
   }
+
+  Widget layerSurface(Layer layer) {
+    return WlrLayerSurface(
+      layer: layer,
+      namespace: "nelly",
+      child: const DemoApp(),
+    );
+  }
 }
 
 class DemoApp extends StatelessWidget {
@@ -12,69 +12,238 @@
 #![deny(clippy::print_stderr, clippy::print_stdout)] // use tracing instead
 
 use std::{
-    convert::Infallible,
+    backtrace::{Backtrace, BacktraceStatus},
+    panic::PanicHookInfo,
     path::Path,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 
-use config::Config;
+use anyhow::bail;
+use calloop::{
+    signals::{Signal, Signals},
+    EventLoop, LoopHandle, LoopSignal,
+};
+pub use config::{Config, DefaultRole};
 use halcyon_embedder::{EmbedderArgs, Halcyon, HalcyonHandler};
-use platform_message::NellyPlatformRequest;
+pub use platform_message::NellyPlatformRequest;
+pub use platform_thread::PlatformThread;
 // use nelly::Nelly;
 use smithay_client_toolkit::{
     reexports::{
-        calloop::{EventLoop, LoopHandle, LoopSignal},
         calloop_wayland_source::WaylandSource,
         client::{globals::registry_queue_init, Connection, QueueHandle},
     },
     registry::{ProvidesRegistryState, RegistryState},
 };
+use tracing::level_filters::LevelFilter;
+use tracing_log::{AsLog, LogTracer};
 use tracing_subscriber::EnvFilter;
-use volito::graphics::RendererConfig;
 
 mod engine_meta {
     include!(concat!(env!("OUT_DIR"), "/engine_meta.rs"));
 }
 
+mod cli;
 mod config;
 mod platform_message;
+mod platform_thread;
 
 const DEFAULT_LOG_FILTER: &str = "nelly=trace,halcyon=trace,volito=trace";
 
+// set by the panic hook, so `run` can tell a shutdown it asked for apart from one it was forced into.
+static PANICKED: AtomicBool = AtomicBool::new(false);
+
 // this is the entrypoint.
 // it just gets paths to the compile output of the Dart half of the app.
 // the actual main() is in `/runner/src/main.rs`
 // but distro packagers may wish to write a different runner to compile the Dart half without Cargo.
+//
+// the paths given here are only defaults; `--assets` and `--app-library` on the command line take precedence.
 pub fn run(assets_path: &Path, app_library: Option<&Path>) -> anyhow::Result<()> {
-    tracing_subscriber::fmt()
-        .compact()
-        .with_env_filter(
-            EnvFilter::builder().parse_lossy(
-                std::env::var("RUST_LOG")
-                    .ok()
-                    .as_deref()
-                    .unwrap_or(DEFAULT_LOG_FILTER),
-            ),
-        )
-        .init();
+    // a host embedding nelly may well have set its own subscriber (and panic hook) already; that's fine, theirs wins.
+    let owns_logging = match tracing::subscriber::set_global_default(
+        tracing_subscriber::fmt()
+            .compact()
+            .with_env_filter(
+                EnvFilter::builder().parse_lossy(
+                    std::env::var("RUST_LOG")
+                        .ok()
+                        .as_deref()
+                        .unwrap_or(DEFAULT_LOG_FILTER),
+                ),
+            )
+            .finish(),
+    ) {
+        Ok(()) => {
+            // a separate step from the subscriber, because a host can have set up a `log` logger and nothing else.
+            // then our subscriber is still the one in charge, so the panic hook goes in regardless.
+            if let Err(err) = LogTracer::builder()
+                .with_max_level(LevelFilter::current().as_log())
+                .init()
+            {
+                tracing::debug!("not forwarding `log` records to tracing: {err}");
+            }
+            true
+        }
+        Err(err) => {
+            tracing::debug!("not installing the default tracing subscriber or panic hook: {err}");
+            false
+        }
+    };
+
+    let args = cli::Args::from_env()?;
 
-    let mut event_loop = EventLoop::try_new()?;
+    let dart_entrypoint_argv = args
+        .dart_args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+
+    let config = Config::load();
+    if let Some(default_role) = args.default_role {
+        config.lock().unwrap().default_role = default_role;
+    }
+
+    let event_loop = EventLoop::try_new()?;
+
+    if owns_logging {
+        install_panic_hook(event_loop.get_signal());
+    }
 
+    // ctrl-c and `systemctl stop` take the same way out as `nelly/graceful_shutdown`,
+    // so the engine and surfaces are torn down properly instead of the process just dying.
+    // this blocks the signals on the current thread, so it must happen before the engine spawns any of its own.
     event_loop
-        .run(
-            None,
-            &mut Nelly::new(assets_path, app_library, &Config::load(), &event_loop)?,
-            |nelly| {
-                _ = nelly; // do absolutely nothing
+        .handle()
+        .insert_source(
+            Signals::new(&[Signal::SIGINT, Signal::SIGTERM])?,
+            |event, (), nelly| {
+                tracing::info!("received {}, shutting down", event.signal());
+                nelly.loop_signal.stop();
             },
         )
+        .map_err(|err| err.error)?;
+
+    run_event_loop(
+        event_loop,
+        NellyArgs {
+            assets_path: args.assets.as_deref().unwrap_or(assets_path),
+            app_library: args.app_library.as_deref().or(app_library),
+            dart_entrypoint: args.dart_entrypoint.as_deref(),
+            dart_entrypoint_argv: &dart_entrypoint_argv,
+        },
+        &config,
+    )?;
+
+    if PANICKED.load(Ordering::Relaxed) {
+        bail!("shut down because a thread panicked");
+    }
+
+    Ok(())
+}
+
+// same as `run`, but doesn't touch any process-global state (the tracing subscriber, the panic hook and signal handling).
+// for library consumers that have already set those up the way they like.
+// it doesn't look at the command line either; that belongs to the host, which passes everything in `args`.
+pub fn run_embedded(args: NellyArgs<'_>) -> anyhow::Result<()> {
+    run_event_loop(EventLoop::try_new()?, args, &Config::load())
+}
+
+fn run_event_loop(
+    mut event_loop: EventLoop<'static, Nelly>,
+    args: NellyArgs<'_>,
+    config: &Arc<Mutex<Config>>,
+) -> anyhow::Result<()> {
+    event_loop
+        .run(None, &mut Nelly::new(args, config, &event_loop)?, |nelly| {
+            _ = nelly; // do absolutely nothing
+        })
         .map_err(Into::into)
 }
 
-struct Nelly {
-    pub qh: QueueHandle<Self>,
-    pub loop_handle: LoopHandle<'static, Nelly>,
-    pub loop_signal: LoopSignal,
+// the default hook writes straight to stderr, which bypasses the log filter and whatever the subscriber does.
+// if the filter wouldn't show our error (say, RUST_LOG=halcyon=debug), the previous hook still gets the panic.
+//
+// a panic on the platform thread unwinds out of the event loop and drops `Nelly` on the way, as usual.
+// one on any other thread would leave the rest of the process running without it,
+// so the loop is stopped too, tearing down the engine and surfaces like `nelly/graceful_shutdown` does.
+fn install_panic_hook(loop_signal: LoopSignal) {
+    let previous = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info: &PanicHookInfo| {
+        if tracing::enabled!(tracing::Level::ERROR) {
+            log_panic(info);
+        } else {
+            previous(info);
+        }
+
+        // only once the message is out, since stopping the loop may well let the process exit
+        PANICKED.store(true, Ordering::Relaxed);
+        loop_signal.stop();
+        loop_signal.wakeup();
+    }));
+}
+
+fn log_panic(info: &PanicHookInfo) {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+
+    let location = info
+        .location()
+        .map_or_else(|| "<unknown>".to_owned(), ToString::to_string);
+
+    // engine callbacks come in on the engine's own threads too; the name is what tells them apart
+    let thread = std::thread::current();
+    let thread = thread.name().unwrap_or("<unnamed>");
+
+    // respects RUST_BACKTRACE, same as the default hook
+    let backtrace = Backtrace::capture();
+
+    if backtrace.status() == BacktraceStatus::Captured {
+        tracing::error!("thread '{thread}' panicked at {location}: {message}\n{backtrace}");
+    } else {
+        tracing::error!("thread '{thread}' panicked at {location}: {message}");
+    }
+}
+
+/// What [`Nelly::new`] needs to start the engine.
+#[derive(Debug, Clone, Copy)]
+pub struct NellyArgs<'a> {
+    /// The `flutter_assets` directory produced by building the Dart half.
+    pub assets_path: &'a Path,
+    /// The AOT-compiled Dart half, if there is one.
+    pub app_library: Option<&'a Path>,
+    /// The Dart function to run instead of `main`.
+    pub dart_entrypoint: Option<&'a str>,
+    /// Arguments passed to the Dart entrypoint.
+    pub dart_entrypoint_argv: &'a [&'a str],
+}
+
+/// The embedder state, driven by a calloop [`EventLoop`].
+///
+/// [`run`] is the usual way to get one of these. A host can also build one with [`Nelly::new`]
+/// and dispatch the event loop itself, but that loop is still nelly's own `EventLoop<Nelly>`;
+/// nelly's views can't be folded into a different event loop or scene graph.
+///
+/// There is no access to the engine from here. Halcyon keeps it private,
+/// so handing embedders an engine handle is blocked on halcyon exposing one.
+///
+/// Everything here belongs to the platform thread: the thread that dispatches the event loop it
+/// was created with. The engine asserts this for its tasks, so don't touch a `Nelly` from anywhere else.
+pub struct Nelly {
+    pub(crate) qh: QueueHandle<Self>,
+    pub(crate) loop_handle: LoopHandle<'static, Nelly>,
+    pub(crate) loop_signal: LoopSignal,
+    connection: Connection,
+    platform_thread: PlatformThread,
+    config: Arc<Mutex<Config>>,
 
     // engine: Engine,
     // pub views: Arc<Mutex<HashMap<ViewId, FlutterWaylandSurface>>>,
@@ -108,26 +277,30 @@ impl HalcyonHandler for Nelly {
 halcyon_embedder::delegate_halcyon!(Nelly);
 
 impl Nelly {
+    /// Connects to the compositor and starts the engine, registering both with `event_loop`.
+    ///
+    /// Nothing happens until that event loop is dispatched, which must be done on this thread.
     pub fn new(
-        assets_path: &Path,
-        app_library: Option<&Path>,
+        args: NellyArgs<'_>,
         config: &Arc<Mutex<Config>>,
         event_loop: &EventLoop<'static, Nelly>,
     ) -> anyhow::Result<Self> {
+        // this also honours WAYLAND_SOCKET (a pre-opened fd, e.g. as handed to us inside Flatpak),
+        // exactly like libwayland does, and only falls back to WAYLAND_DISPLAY when it's unset.
         let connection = Connection::connect_to_env()?;
 
-        let (globals, queue) = registry_queue_init::<Nelly>(&connection).unwrap();
+        let (globals, queue) = registry_queue_init::<Nelly>(&connection)?;
 
         let qh = queue.handle();
 
         let registry_state = RegistryState::new(&globals);
         let halcyon = Halcyon::new(
             EmbedderArgs {
-                assets_path,
+                assets_path: args.assets_path,
                 icu_data_path: Path::new(crate::engine_meta::ICUDTL_DAT),
-                app_library,
-                custom_dart_entrypoint: None,
-                dart_entrypoint_argv: &[],
+                app_library: args.app_library,
+                custom_dart_entrypoint: args.dart_entrypoint,
+                dart_entrypoint_argv: args.dart_entrypoint_argv,
                 renderer: halcyon_embedder::RendererArgs::Vulkan {
                     application_name: Some("nelly"),
                     application_version: 0,
@@ -138,12 +311,15 @@ impl Nelly {
             qh.clone(),
         )?;
 
-        WaylandSource::new(connection, queue).insert(event_loop.handle())?;
+        WaylandSource::new(connection.clone(), queue).insert(event_loop.handle())?;
 
         Ok(Self {
             qh,
             loop_handle: event_loop.handle(),
             loop_signal: event_loop.get_signal(),
+            connection,
+            platform_thread: PlatformThread::new(event_loop)?,
+            config: Arc::clone(config),
 
             // engine,
             // views,
@@ -157,4 +333,19 @@ impl Nelly {
             // layer_shell,
         })
     }
+
+    /// A [`Send`] handle for getting work back onto the platform thread from other threads.
+    #[must_use]
+    pub fn platform_thread(&self) -> PlatformThread {
+        self.platform_thread.clone()
+    }
+
+    /// Blocks until the compositor has processed every request sent so far.
+    ///
+    /// This stalls the platform thread for a full round trip to the compositor,
+    /// so only reach for it where the ordering actually matters.
+    pub fn roundtrip(&self) -> std::io::Result<()> {
+        self.connection.roundtrip().map_err(std::io::Error::other)?;
+        Ok(())
+    }
 }
@@ -0,0 +1,25 @@
+import "package:halcyon/binary.dart";
+
+// must match `DefaultRole` in `config.rs`, in the same order.
+enum DefaultRole {
+  toplevel,
+  fullscreenToplevel,
+  backgroundLayer,
+  topLayer,
+  overlayLayer,
+}
+
+Future<DefaultRole> defaultRole() async {
+  final response =
+      await sendPlatformMessage("nelly/default_role", (writer) {});
+
+  // an embedder that predates this message answers with nothing; the handshake reports that already.
+  if (response.isFinished) {
+    return DefaultRole.toplevel;
+  }
+
+  final role = DefaultRole.values[response.readUint32()];
+  response.assertFinished();
+
+  return role;
+}
@@ -0,0 +1,32 @@
+use std::{
+    ffi::CStr,
+    io::{Read, Result, Seek, Write},
+};
+
+use halcyon_embedder::platform_message::{
+    binary::{BinaryDecodable, BinaryEncodable, BinaryReader, BinaryWriter},
+    ManagedPlatformRequest, PlatformMessageChannel,
+};
+
+use crate::Nelly;
+
+#[derive(Debug)]
+pub struct GetDefaultRole;
+
+impl BinaryDecodable for GetDefaultRole {
+    fn decode(_reader: &mut BinaryReader<impl Read + Seek>) -> Result<Self> {
+        Ok(GetDefaultRole)
+    }
+}
+
+impl PlatformMessageChannel for GetDefaultRole {
+    const CHANNEL: &'static CStr = c"nelly/default_role";
+}
+
+impl ManagedPlatformRequest<Nelly> for GetDefaultRole {
+    fn run(self, nelly: &mut Nelly, writer: &mut BinaryWriter<impl Write>) -> Result<()> {
+        let default_role = nelly.config.lock().unwrap().default_role;
+
+        (default_role as u32).encode(writer)
+    }
+}
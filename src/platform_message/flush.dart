@@ -0,0 +1,10 @@
+import "package:halcyon/binary.dart";
+
+// completes once the compositor has processed every request made before it,
+// e.g. so that a cursor change is actually visible before taking a screenshot.
+// each call is a full round trip to the compositor; don't call this every frame.
+Future<void> flush() async {
+  final response = await sendPlatformMessage("nelly/flush", (writer) {});
+
+  response.assertFinished();
+}
@@ -0,0 +1,30 @@
+use std::{
+    ffi::CStr,
+    io::{Read, Result, Seek, Write},
+};
+
+use halcyon_embedder::platform_message::{
+    binary::{BinaryDecodable, BinaryReader, BinaryWriter},
+    ManagedPlatformRequest, PlatformMessageChannel,
+};
+
+use crate::Nelly;
+
+#[derive(Debug)]
+pub struct Flush;
+
+impl BinaryDecodable for Flush {
+    fn decode(_reader: &mut BinaryReader<impl Read + Seek>) -> Result<Self> {
+        Ok(Flush)
+    }
+}
+
+impl PlatformMessageChannel for Flush {
+    const CHANNEL: &'static CStr = c"nelly/flush";
+}
+
+impl ManagedPlatformRequest<Nelly> for Flush {
+    fn run(self, nelly: &mut Nelly, _writer: &mut BinaryWriter<impl Write>) -> Result<()> {
+        nelly.roundtrip()
+    }
+}
@@ -0,0 +1,37 @@
+import "dart:async";
+
+import "package:halcyon/binary.dart";
+
+// bump this whenever the encoding of any `nelly/*` message changes.
+// must match `PROTOCOL_VERSION` in `handshake.rs`.
+const protocolVersion = 2;
+
+// nothing awaits this, so a mismatch is reported through the zone's print (which the tracing zone sends to the log);
+// throwing would only turn it into an uncaught error with no context.
+Future<void> handshake() async {
+  final response = await sendPlatformMessage("nelly/handshake", (writer) {
+    writer.writeUint32(protocolVersion);
+  });
+
+  // an embedder from before this message existed has no handler for it,
+  // and unknown channels are answered with an empty response.
+  if (response.isFinished) {
+    Zone.current.print(
+      "platform message protocol mismatch: "
+      "the embedder predates the handshake "
+      "(protocol version $protocolVersion expected)",
+    );
+    return;
+  }
+
+  final embedderVersion = response.readUint32();
+  response.assertFinished();
+
+  if (embedderVersion != protocolVersion) {
+    Zone.current.print(
+      "platform message protocol mismatch: "
+      "this bundle speaks version $protocolVersion, "
+      "but the embedder speaks version $embedderVersion",
+    );
+  }
+}
@@ -0,0 +1,49 @@
+use std::{
+    ffi::CStr,
+    io::{Read, Result, Seek, Write},
+};
+
+use halcyon_embedder::platform_message::{
+    binary::{BinaryDecodable, BinaryEncodable, BinaryReader, BinaryWriter},
+    ManagedPlatformRequest, PlatformMessageChannel,
+};
+
+use crate::Nelly;
+
+// bump this whenever the encoding of any `nelly/*` message changes.
+// must match `protocolVersion` in `handshake.dart`.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+#[derive(Debug)]
+pub struct Handshake {
+    dart_version: u32,
+}
+
+impl BinaryDecodable for Handshake {
+    fn decode(reader: &mut BinaryReader<impl Read + Seek>) -> Result<Self> {
+        Ok(Handshake {
+            dart_version: u32::decode(reader)?,
+        })
+    }
+}
+
+impl PlatformMessageChannel for Handshake {
+    const CHANNEL: &'static CStr = c"nelly/handshake";
+}
+
+impl ManagedPlatformRequest<Nelly> for Handshake {
+    fn run(self, _nelly: &mut Nelly, writer: &mut BinaryWriter<impl Write>) -> Result<()> {
+        if self.dart_version == PROTOCOL_VERSION {
+            tracing::debug!("platform message protocol version {PROTOCOL_VERSION}");
+        } else {
+            tracing::error!(
+                "platform message protocol mismatch: embedder speaks version {PROTOCOL_VERSION}, \
+                 but the dart bundle speaks version {}. \
+                 the dart half was probably built against a different nelly; rebuild it.",
+                self.dart_version
+            );
+        }
+
+        PROTOCOL_VERSION.encode(writer)
+    }
+}
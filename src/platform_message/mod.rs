@@ -1,12 +1,18 @@
 use halcyon_embedder::multiplexed_platform_request;
 
+mod default_role;
+mod flush;
+mod handshake;
 mod shutdown;
 
 multiplexed_platform_request!(
-    pub(crate) enum NellyPlatformRequest {
+    pub enum NellyPlatformRequest {
         type State = crate::Nelly;
 
         @single {
+            GetDefaultRole(default_role::GetDefaultRole),
+            Flush(flush::Flush),
+            Handshake(handshake::Handshake),
             Shutdown(shutdown::Shutdown),
         }
     }
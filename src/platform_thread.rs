@@ -0,0 +1,46 @@
+use calloop::{
+    channel::{self, Channel, Event, Sender},
+    EventLoop,
+};
+
+use crate::Nelly;
+
+type PlatformTask = Box<dyn FnOnce(&mut Nelly) + Send>;
+
+/// A handle for running code on the platform thread from anywhere else.
+///
+/// [`Nelly`] and the engine must only be touched on the thread dispatching the event loop,
+/// so work that starts elsewhere (e.g. blocking I/O) should hand its result back through this.
+///
+/// The only way to get one is [`Nelly::platform_thread`], so it's meant for code that already has
+/// a `Nelly` in hand: platform message handlers, or a host that builds one with [`Nelly::new`].
+/// [`run`](crate::run) and [`run_embedded`](crate::run_embedded) never hand a `Nelly` out.
+#[derive(Clone)]
+pub struct PlatformThread {
+    sender: Sender<PlatformTask>,
+}
+
+impl PlatformThread {
+    pub(crate) fn new(event_loop: &EventLoop<'static, Nelly>) -> anyhow::Result<Self> {
+        let (sender, channel): (_, Channel<PlatformTask>) = channel::channel();
+
+        event_loop
+            .handle()
+            .insert_source(channel, |event, (), nelly| {
+                if let Event::Msg(task) = event {
+                    task(nelly);
+                }
+            })
+            .map_err(|err| err.error)?;
+
+        Ok(Self { sender })
+    }
+
+    /// Queues `task` to run on the platform thread during the next event loop dispatch.
+    ///
+    /// Returns `false` if the event loop is already gone, in which case `task` is dropped without running.
+    #[must_use]
+    pub fn post(&self, task: impl FnOnce(&mut Nelly) + Send + 'static) -> bool {
+        self.sender.send(Box::new(task)).is_ok()
+    }
+}